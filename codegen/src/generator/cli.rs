@@ -0,0 +1,242 @@
+use std::fs::File;
+use std::io::{Write, BufWriter};
+use std::path::Path;
+
+use inflector::Inflector;
+
+use botocore::{Service, ShapeType};
+
+use super::{generate_field_name, mutate_type_name, FileWriter, IoResult};
+
+/// Generates an `argh`-based command-line binary for a `Service`, walking the same
+/// `Service`/`Operation` metadata the Rust protocol generators use. Each operation becomes a
+/// `#[derive(FromArgs)]` subcommand, and a top-level enum dispatches to the generated client
+/// method. Scalar fields become plain `#[argh(option)]` flags; struct/list/map-typed fields
+/// (which don't implement `FromStr`) instead take a path to a JSON file holding the value.
+pub struct GenerateCli;
+
+impl GenerateCli {
+    /// Write a CLI binary for `service` to `output_path`.
+    pub fn generate_cli(&self, service: &Service, output_path: &Path) -> IoResult {
+        let output_file = File::create(output_path).expect(&format!(
+            "Couldn't open file for writing: {:?}",
+            output_path,
+        ));
+
+        let mut writer = BufWriter::new(output_file);
+
+        writeln!(writer, "#[allow(warnings)]
+            use argh::FromArgs;
+
+            use rusoto_core::{{Region, RusotoFuture}};
+            use rusoto_credential::DefaultCredentialsProvider;
+            use rusoto_{module_name}::*;
+        ", module_name = service.module_name())?;
+
+        self.generate_subcommands(&mut writer, service)?;
+        self.generate_top_level(&mut writer, service)?;
+        self.generate_main(&mut writer, service)?;
+
+        Ok(())
+    }
+
+    fn generate_subcommands(&self, writer: &mut FileWriter, service: &Service) -> IoResult {
+        for (operation_name, operation) in &service.operations {
+            let subcommand_name = mutate_type_name(operation_name);
+            let command_name = operation_name.to_kebab_case();
+
+            let fields = match operation.input {
+                Some(ref input_shape_ref) => {
+                    let input_shape = &service.shapes[&input_shape_ref.shape];
+                    self.generate_argh_fields(service, input_shape)
+                }
+                None => String::new(),
+            };
+
+            writeln!(writer,
+                "#[derive(FromArgs)]
+                #[argh(subcommand, name = \"{command_name}\")]
+                /// {subcommand_name}
+                pub struct {subcommand_name}Args {{
+                    {fields}
+                }}
+                ",
+                command_name = command_name,
+                subcommand_name = subcommand_name,
+                fields = fields,
+            )?;
+
+            if let Some(ref input_shape_ref) = operation.input {
+                let input_shape = &service.shapes[&input_shape_ref.shape];
+                let input_type_name = mutate_type_name(&input_shape_ref.shape);
+                let conversions = self.generate_argh_conversions(service, input_shape);
+
+                writeln!(writer,
+                    "impl From<{subcommand_name}Args> for {input_type_name} {{
+                        fn from(args: {subcommand_name}Args) -> Self {{
+                            {input_type_name} {{
+                                {conversions}
+                                ..Default::default()
+                            }}
+                        }}
+                    }}
+                    ",
+                    subcommand_name = subcommand_name,
+                    input_type_name = input_type_name,
+                    conversions = conversions,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Map each CLI field back onto the generated request struct's field. Blob fields are read
+    /// from the file path the user passed on the command line; struct/list/map fields are read
+    /// as JSON from the file path the user passed, since `argh` can't parse them directly. A
+    /// non-required field's CLI arg is itself `Option<String>` (see `generate_argh_fields`), so
+    /// the file read has to happen inside a `.map(..)` rather than unconditionally.
+    fn generate_argh_conversions(&self, service: &Service, shape: &::botocore::Shape) -> String {
+        match shape.members {
+            None => String::new(),
+            Some(ref members) => members.iter().filter_map(|(member_name, member)| {
+                if member.deprecated == Some(true) {
+                    return None;
+                }
+
+                let name = generate_field_name(member_name);
+                let name = if name == "type" { "aws_type".to_owned() } else { name };
+                let field = format!("args.{}", name);
+                let required = shape.required(member_name);
+
+                let read_blob = |path: &str| format!(
+                    "std::fs::read({path}).expect(\"couldn't read file\")",
+                    path = path,
+                );
+                let read_json = |path: &str| format!(
+                    "serde_json::from_str(&std::fs::read_to_string({path}).expect(\"couldn't read file\")).expect(\"couldn't parse JSON\")",
+                    path = path,
+                );
+
+                let value = match (service.shape_type_for_member(member), required) {
+                    (Some(ShapeType::Blob), true) => read_blob(&field),
+                    (Some(ShapeType::Blob), false) => format!("{field}.map(|path| {read})", field = field, read = read_blob("path")),
+                    (Some(ShapeType::Structure), true) | (Some(ShapeType::List), true) | (Some(ShapeType::Map), true) =>
+                        read_json(&field),
+                    (Some(ShapeType::Structure), false) | (Some(ShapeType::List), false) | (Some(ShapeType::Map), false) =>
+                        format!("{field}.map(|path| {read})", field = field, read = read_json("path")),
+                    _ => field,
+                };
+
+                Some(format!("{name}: {value},", name = name, value = value))
+            }).collect::<Vec<String>>().join("\n"),
+        }
+    }
+
+    fn generate_argh_fields(&self, service: &Service, shape: &::botocore::Shape) -> String {
+        match shape.members {
+            None => String::new(),
+            Some(ref members) => members.iter().filter_map(|(member_name, member)| {
+                if member.deprecated == Some(true) {
+                    return None;
+                }
+
+                let name = generate_field_name(member_name);
+                let name = if name == "type" { "aws_type".to_owned() } else { name };
+
+                // `argh(option)` requires the field type implement `FromStr`, which struct/
+                // list/map-typed members don't, so those take a path to a JSON file instead.
+                let (rust_type, doc_suffix) = match service.shape_type_for_member(member) {
+                    Some(ShapeType::Blob) => ("String".to_owned(), " (path to a file)"),
+                    Some(ShapeType::Structure) | Some(ShapeType::List) | Some(ShapeType::Map) =>
+                        ("String".to_owned(), " (path to a JSON file)"),
+                    _ => (mutate_type_name(&member.shape), ""),
+                };
+
+                let doc = member.documentation.as_ref()
+                    .map(|d| format!("/// {}{}\n", d.replace('\n', " "), doc_suffix))
+                    .unwrap_or_else(|| if doc_suffix.is_empty() {
+                        String::new()
+                    } else {
+                        format!("///{}\n", doc_suffix)
+                    });
+
+                if shape.required(member_name) {
+                    Some(format!("{doc}#[argh(option)]\npub {name}: {rust_type},", doc = doc, name = name, rust_type = rust_type))
+                } else {
+                    Some(format!("{doc}#[argh(option)]\npub {name}: Option<{rust_type}>,", doc = doc, name = name, rust_type = rust_type))
+                }
+            }).collect::<Vec<String>>().join("\n"),
+        }
+    }
+
+    fn generate_top_level(&self, writer: &mut FileWriter, service: &Service) -> IoResult {
+        let variants = service.operations.keys().map(|operation_name| {
+            let subcommand_name = mutate_type_name(operation_name);
+            format!("{name}({name}Args),", name = subcommand_name)
+        }).collect::<Vec<String>>().join("\n");
+
+        writeln!(writer,
+            "#[derive(FromArgs)]
+            #[argh(subcommand)]
+            pub enum Operation {{
+                {variants}
+            }}
+
+            #[derive(FromArgs)]
+            /// A command-line client for the {service_name} API.
+            pub struct Cli {{
+                #[argh(option, default = \"String::from(\\\"us-east-1\\\")\")]
+                /// the AWS region to target
+                pub region: String,
+
+                #[argh(subcommand)]
+                pub operation: Operation,
+            }}
+            ",
+            variants = variants,
+            service_name = service.metadata.service_full_name,
+        )
+    }
+
+    fn generate_main(&self, writer: &mut FileWriter, service: &Service) -> IoResult {
+        let dispatch_arms = service.operations.iter().map(|(operation_name, operation)| {
+            let subcommand_name = mutate_type_name(operation_name);
+            let method_name = generate_field_name(operation_name);
+
+            // An operation with no input shape has no `From<{subcommand}Args>` impl at all
+            // (see `generate_subcommands`), so there's nothing to convert and no argument to
+            // pass — mirror the generated Rust client's own no-input method signature.
+            let (pattern, call) = match operation.input {
+                Some(_) => ("args", format!("client.{method_name}(args.into())", method_name = method_name)),
+                None => ("_args", format!("client.{method_name}()", method_name = method_name)),
+            };
+
+            format!(
+                "Operation::{subcommand_name}({pattern}) => {{
+                    let result = {call}.sync();
+                    println!(\"{{}}\", serde_json::to_string_pretty(&result.unwrap()).unwrap());
+                }}",
+                subcommand_name = subcommand_name,
+                pattern = pattern,
+                call = call,
+            )
+        }).collect::<Vec<String>>().join("\n");
+
+        writeln!(writer,
+            "fn main() {{
+                let cli: Cli = argh::from_env();
+                let region: Region = cli.region.parse().expect(\"invalid region\");
+                let credentials_provider = DefaultCredentialsProvider::new().unwrap();
+                let dispatcher = rusoto_core::HttpClient::new().unwrap();
+                let client = {type_name}::new(dispatcher, credentials_provider, region);
+
+                match cli.operation {{
+                    {dispatch_arms}
+                }}
+            }}
+            ",
+            type_name = service.client_type_name(),
+            dispatch_arms = dispatch_arms,
+        )
+    }
+}
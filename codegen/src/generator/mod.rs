@@ -12,6 +12,8 @@ use self::rest_xml::RestXmlGenerator;
 use self::error_types::{GenerateErrorTypes, JsonErrorTypes, XmlErrorTypes};
 use self::tests::generate_tests;
 use self::type_filter::filter_types;
+use self::python::GeneratePythonBindings;
+use self::cli::GenerateCli;
 
 mod error_types;
 mod json;
@@ -22,6 +24,8 @@ mod rest_xml;
 mod xml_payload_parser;
 mod rest_response_parser;
 mod type_filter;
+mod python;
+mod cli;
 
 type FileWriter = BufWriter<File>;
 type IoResult = ::std::io::Result<()>;
@@ -83,6 +87,23 @@ pub fn generate_source(service: &Service, output_path: &Path) -> IoResult {
     }
 }
 
+/// Given a botocore `Service` object, emit PyO3 bindings to the specified path.
+///
+/// This walks the same `Service`/`Shape` model as `generate_source`, so it stays in sync with
+/// the botocore model for free, but produces `#[pyclass]`/`#[pymethods]` Python bindings
+/// instead of a Rust client module.
+pub fn generate_python_source(service: &Service, output_path: &Path) -> IoResult {
+    GeneratePythonBindings.generate_source(service, output_path)
+}
+
+/// Given a botocore `Service` object, emit an `argh`-based CLI binary to the specified path.
+///
+/// Every operation becomes a `FromArgs` subcommand built from the same request shape the
+/// Rust client methods use, so the CLI stays in sync with the generated library for free.
+pub fn generate_cli_source(service: &Service, output_path: &Path) -> IoResult {
+    GenerateCli.generate_cli(service, output_path)
+}
+
 /// Translate a botocore field name to something rust-idiomatic and
 /// escape reserved words with an underscore
 pub fn generate_field_name(member_name: &str) -> String {
@@ -112,7 +133,6 @@ fn generate<P, E>(writer: &mut FileWriter, service: &Service, protocol_generator
           E: GenerateErrorTypes {
 
     writeln!(writer, "#[allow(warnings)]
-        use hyper::Client;
         use hyper::status::StatusCode;
         use request::DispatchSignedRequest;
         use region;
@@ -0,0 +1,309 @@
+use std::fs::File;
+use std::io::{Write, BufWriter};
+use std::path::Path;
+
+use botocore::{Service, Shape, ShapeType};
+
+use super::{capitalize_first, generate_field_name, mutate_type_name, FileWriter, IoResult};
+
+/// Emits PyO3 bindings for a `Service` by walking the same botocore `Shape` model the Rust
+/// protocol generators use. Every `Structure` shape becomes a `#[pyclass]`, and every
+/// operation becomes a `#[pymethods]` method that converts into the generated request type,
+/// blocks on the resulting `RusotoFuture` off the GIL, and converts the response back.
+pub struct GeneratePythonBindings;
+
+impl GeneratePythonBindings {
+    /// Write the bindings for `service` to `output_path`, mirroring the layout of
+    /// `generate_source`.
+    pub fn generate_source(&self, service: &Service, output_path: &Path) -> IoResult {
+        let output_file = File::create(output_path).expect(&format!(
+            "Couldn't open file for writing: {:?}",
+            output_path,
+        ));
+
+        let mut writer = BufWriter::new(output_file);
+
+        writeln!(writer, "#[allow(warnings)]
+            use pyo3::prelude::*;
+            use pyo3::exceptions::PyRuntimeError;
+
+            use rusoto_core::{{Region, RusotoFuture}};
+            use rusoto_core::credential::CredentialsError;
+            use rusoto_core::request::HttpDispatchError;
+            use rusoto_credential::DefaultCredentialsProvider;
+
+            /// Drive a `RusotoFuture` to completion off the GIL, so a single slow request does
+            /// not block other Python threads, and hand the resolved value back as a `PyResult`.
+            fn await_future<T, E>(py: Python, future: RusotoFuture<T, E>) -> PyResult<T>
+                where T: Send + 'static,
+                      E: ::std::fmt::Debug + From<CredentialsError> + From<HttpDispatchError> + Send + 'static
+            {{
+                py.allow_threads(move || future.sync())
+                    .map_err(|e| PyRuntimeError::new_err(format!(\"{{:?}}\", e)))
+            }}
+        ")?;
+
+        self.generate_types(&mut writer, service)?;
+        self.generate_conversions(&mut writer, service)?;
+        self.generate_client(&mut writer, service)?;
+
+        Ok(())
+    }
+
+    fn generate_types(&self, writer: &mut FileWriter, service: &Service) -> IoResult {
+        for (name, shape) in &service.shapes {
+            let type_name = mutate_type_name(name);
+
+            if shape.exception() || type_name == "String" {
+                continue;
+            }
+
+            if shape.shape_type != ShapeType::Structure {
+                continue;
+            }
+
+            writeln!(writer, "{}", self.generate_struct(service, &type_name, shape))?;
+        }
+        Ok(())
+    }
+
+    fn generate_struct(&self, service: &Service, name: &str, shape: &Shape) -> String {
+        if shape.members.is_none() || shape.members.as_ref().unwrap().is_empty() {
+            return format!(
+                "#[pyclass]
+                #[derive(Clone, Default)]
+                pub struct {name};
+                ",
+                name = name,
+            );
+        }
+
+        format!(
+            "#[pyclass]
+            #[derive(Clone, Default)]
+            pub struct {name} {{
+                {fields}
+            }}
+            ",
+            name = name,
+            fields = self.generate_struct_fields(service, shape),
+        )
+    }
+
+    fn generate_struct_fields(&self, service: &Service, shape: &Shape) -> String {
+        shape.members.as_ref().unwrap().iter().filter_map(|(member_name, member)| {
+            if member.deprecated == Some(true) {
+                return None;
+            }
+
+            // Match `generate_field_name`/`generate_struct_fields` in mod.rs: `type` is a
+            // reserved word, so a member literally named `Type` has to be renamed.
+            let name = generate_field_name(member_name);
+            let name = if name == "type" { "aws_type".to_owned() } else { name };
+
+            let python_type = self.map_shape_to_python_type(service, &member.shape);
+
+            if shape.required(member_name) {
+                Some(format!("#[pyo3(get, set)]\npub {}: {},", name, python_type))
+            } else {
+                Some(format!("#[pyo3(get, set)]\npub {}: Option<{}>,", name, python_type))
+            }
+        }).collect::<Vec<String>>().join("\n")
+    }
+
+    /// Map a member's shape to a type `pyo3` can convert to and from a Python object, the same
+    /// way `generate_primitive_type`/`generate_list`/`generate_map` map a shape to a Rust type
+    /// for the generated Rust structs. Structures map to the `#[pyclass]` generated for them;
+    /// lists and maps recurse into their element shape rather than naming themselves, since
+    /// only `Structure` shapes get a `#[pyclass]`. Blobs map to `Vec<u8>`, exactly like
+    /// `generate_primitive_type` in `mod.rs` maps them for the generated Rust structs, rather
+    /// than `Py<PyBytes>` — `Py<T>` has no `Default` impl, which `#[derive(Default)]` on every
+    /// generated `#[pyclass]` needs, and keeping both sides `Vec<u8>` means the field doesn't
+    /// need a GIL-bound conversion in `generate_field_conversions` either.
+    fn map_shape_to_python_type(&self, service: &Service, shape_name: &str) -> String {
+        let shape = &service.shapes[shape_name];
+
+        match shape.shape_type {
+            ShapeType::Blob => "Vec<u8>".to_owned(),
+            ShapeType::Timestamp => "String".to_owned(),
+            ShapeType::Boolean => "bool".to_owned(),
+            ShapeType::Double => "f64".to_owned(),
+            ShapeType::Float => "f32".to_owned(),
+            ShapeType::Integer => "i32".to_owned(),
+            ShapeType::Long => "i64".to_owned(),
+            ShapeType::String => "String".to_owned(),
+            ShapeType::List => {
+                format!("Vec<{}>", self.map_shape_to_python_type(service, shape.member_type()))
+            }
+            ShapeType::Map => {
+                format!(
+                    "::std::collections::HashMap<{}, {}>",
+                    self.map_shape_to_python_type(service, shape.key_type()),
+                    self.map_shape_to_python_type(service, shape.value_type()),
+                )
+            }
+            ShapeType::Structure => capitalize_first(mutate_type_name(shape_name)),
+        }
+    }
+
+    /// For every non-exception `Structure` shape (the same set `generate_types` emits a
+    /// `#[pyclass]` for), emit a pair of `From` impls converting between that `#[pyclass]` and
+    /// the real Rust type, so nested structure members and operation inputs/outputs alike can
+    /// be converted with `.into()`.
+    fn generate_conversions(&self, writer: &mut FileWriter, service: &Service) -> IoResult {
+        for (name, shape) in &service.shapes {
+            let type_name = mutate_type_name(name);
+
+            if shape.exception() || type_name == "String" || shape.shape_type != ShapeType::Structure {
+                continue;
+            }
+
+            let to_rust = self.generate_field_conversions(service, shape, "py_value", true);
+            let to_python = self.generate_field_conversions(service, shape, "rust_value", false);
+
+            writeln!(writer,
+                "impl From<{name}> for ::rusoto_{module_name}::{name} {{
+                    fn from(py_value: {name}) -> Self {{
+                        ::rusoto_{module_name}::{name} {{
+                            {to_rust}
+                            ..Default::default()
+                        }}
+                    }}
+                }}
+
+                impl From<::rusoto_{module_name}::{name}> for {name} {{
+                    fn from(rust_value: ::rusoto_{module_name}::{name}) -> Self {{
+                        {name} {{
+                            {to_python}
+                            ..Default::default()
+                        }}
+                    }}
+                }}
+                ",
+                name = type_name,
+                module_name = service.module_name(),
+                to_rust = to_rust,
+                to_python = to_python,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn generate_field_conversions(&self, service: &Service, shape: &Shape, source: &str, to_rust: bool) -> String {
+        match shape.members {
+            None => String::new(),
+            Some(ref members) => members.iter().filter_map(|(member_name, member)| {
+                if member.deprecated == Some(true) {
+                    return None;
+                }
+
+                let name = generate_field_name(member_name);
+                let name = if name == "type" { "aws_type".to_owned() } else { name };
+                let field = format!("{}.{}", source, name);
+
+                let value = if shape.required(member_name) {
+                    self.convert_value_expr(service, &member.shape, &field, to_rust)
+                } else {
+                    // `field` is already `Option<...>` on both sides (see `generate_struct_fields`
+                    // and `mod.rs`'s own `generate_struct_fields`), so only the wrapped value
+                    // needs converting.
+                    let inner = self.convert_value_expr(service, &member.shape, "v", to_rust);
+                    format!("{field}.map(|v| {inner})", field = field, inner = inner)
+                };
+
+                Some(format!("{name}: {value},", name = name, value = value))
+            }).collect::<Vec<String>>().join("\n"),
+        }
+    }
+
+    /// Convert a single (non-`Option`) value of the given shape from the `#[pyclass]`
+    /// representation to the real Rust type, or back, mirroring `map_shape_to_python_type`'s
+    /// recursion. A bare `.into()` only lifts through one level of `From`, so `Vec<Structure>`
+    /// and `HashMap<_, Structure>` members (e.g. S3 `ListObjectsOutput.contents`) need to map
+    /// over their elements instead of converting the whole collection in one shot.
+    fn convert_value_expr(&self, service: &Service, shape_name: &str, expr: &str, to_rust: bool) -> String {
+        let shape = &service.shapes[shape_name];
+
+        match shape.shape_type {
+            ShapeType::List => {
+                let inner = self.convert_value_expr(service, shape.member_type(), "v", to_rust);
+                format!("{expr}.into_iter().map(|v| {inner}).collect::<Vec<_>>()", expr = expr, inner = inner)
+            }
+            ShapeType::Map => {
+                let inner = self.convert_value_expr(service, shape.value_type(), "v", to_rust);
+                format!(
+                    "{expr}.into_iter().map(|(k, v)| (k, {inner})).collect::<::std::collections::HashMap<_, _>>()",
+                    expr = expr,
+                    inner = inner,
+                )
+            }
+            _ => format!("{expr}.into()", expr = expr),
+        }
+    }
+
+    fn generate_client(&self, writer: &mut FileWriter, service: &Service) -> IoResult {
+        writeln!(writer,
+            "#[pyclass]
+            pub struct {type_name} {{
+                inner: ::rusoto_{module_name}::{type_name}<DefaultCredentialsProvider, ::rusoto_core::HttpClient>,
+            }}
+
+            #[pymethods]
+            impl {type_name} {{
+                #[new]
+                fn new(region_name: &str) -> PyResult<Self> {{
+                    let region: Region = region_name.parse()
+                        .map_err(|e| PyRuntimeError::new_err(format!(\"{{:?}}\", e)))?;
+                    let credentials_provider = DefaultCredentialsProvider::new()
+                        .map_err(|e| PyRuntimeError::new_err(format!(\"{{:?}}\", e)))?;
+                    let dispatcher = ::rusoto_core::HttpClient::new()
+                        .map_err(|e| PyRuntimeError::new_err(format!(\"{{:?}}\", e)))?;
+                    Ok({type_name} {{
+                        inner: ::rusoto_{module_name}::{type_name}::new(dispatcher, credentials_provider, region),
+                    }})
+                }}
+            ",
+            type_name = service.client_type_name(),
+            module_name = service.module_name(),
+        )?;
+
+        self.generate_methods(writer, service)?;
+
+        writeln!(writer, "}}")
+    }
+
+    fn generate_methods(&self, writer: &mut FileWriter, service: &Service) -> IoResult {
+        for (operation_name, operation) in &service.operations {
+            let method_name = generate_field_name(operation_name);
+
+            // Mirror the generated Rust client's own convention (see `mod.rs`'s protocol
+            // generators): an operation with no input shape takes no request parameter at
+            // all, rather than a spurious `Default::default()` argument.
+            let (arg, convert_arg) = match operation.input {
+                Some(ref input_shape_ref) => (
+                    format!(", request: {}", mutate_type_name(&input_shape_ref.shape)),
+                    "request.into()",
+                ),
+                None => (String::new(), ""),
+            };
+
+            let output_type = match operation.output {
+                Some(ref output_shape_ref) => mutate_type_name(&output_shape_ref.shape),
+                None => "()".to_owned(),
+            };
+
+            writeln!(writer,
+                "fn {method_name}(&self, py: Python{arg}) -> PyResult<{output_type}> {{
+                    let future = self.inner.{method_name}({convert_arg});
+                    Ok(await_future(py, future)?.into())
+                }}
+                ",
+                method_name = method_name,
+                arg = arg,
+                convert_arg = convert_arg,
+                output_type = output_type,
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,8 +1,9 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use futures::{Future, IntoFuture, Poll, Async};
 use futures::sync::oneshot::spawn;
 use tokio::runtime::Runtime;
+use tokio::timer::Delay;
 
 use super::credential::CredentialsError;
 use super::client::{TimeoutFuture, SignAndDispatchError};
@@ -12,8 +13,27 @@ lazy_static! {
     static ref FALLBACK_RUNTIME: Runtime = Runtime::new().unwrap();
 }
 
+/// The type-erased dispatch future used when no concrete dispatch-future type is known,
+/// e.g. after `RusotoFuture::boxed()` or for the mocking `From<Result<_, _>>` path.
+pub type BoxDispatchFuture = Box<TimeoutFuture<Item=HttpResponse, Error=SignAndDispatchError> + Send>;
+
+/// The type-erased response-handler future used by the same fallback path.
+pub type BoxHandlerFuture<T, E> = Box<Future<Item=T, Error=E> + Send>;
+
+/// The type-erased dispatch factory used by the same fallback path. Unlike `BoxDispatchFuture`,
+/// this can be called more than once, which is what lets `.with_retry()` re-run sign-and-dispatch.
+pub type BoxDispatch<F> = Box<Fn() -> F + Send>;
+
 /// Future that is returned from all rusoto service APIs.
 ///
+/// `RusotoFuture` is generic over the concrete dispatch-future type `F`, the response-handler
+/// closure `H` (which produces the handler future `R`), and the dispatch factory `D` that
+/// produces a fresh `F` (needed to re-run sign-and-dispatch on retry), so the sign, dispatch,
+/// and parse steps can be polled in place without boxing on the hot path. Generated client
+/// methods use the defaults, which fall back to type-erased `Box<Future + Send>`s so every
+/// method can share the single concrete `RusotoFuture<T, E>` signature; call `.boxed()` to
+/// erase a more specific `RusotoFuture<T, E, F, R, H, D>` back down to that signature.
+///
 /// ## Mocking
 ///
 /// To mock service traits, you can use the `From` implementation to create `RusotoFuture`
@@ -48,19 +68,50 @@ lazy_static! {
 ///     ...
 /// }
 /// ```
-pub struct RusotoFuture<T, E> {
-    state: Option<RusotoFutureState<T, E>>
+pub struct RusotoFuture<
+    T, E,
+    F = BoxDispatchFuture,
+    R = BoxHandlerFuture<T, E>,
+    H = fn(HttpResponse) -> R,
+    D = BoxDispatch<F>,
+> {
+    state: Option<RusotoFutureState<T, E, F, R, H, D>>,
+    retry_policy: Option<RetryPolicy<E>>,
+    // Remembered so a retry's freshly-dispatched future (built from scratch by `dispatch()`,
+    // which knows nothing about a timeout set on the future that failed) still gets it applied.
+    timeout: Option<Duration>,
 }
 
 pub fn new<T, E>(
-        future: Box<TimeoutFuture<Item=HttpResponse, Error=SignAndDispatchError> + Send>,
-        handler: fn(HttpResponse) -> Box<Future<Item=T, Error=E> + Send>
+        dispatch: BoxDispatch<BoxDispatchFuture>,
+        handler: fn(HttpResponse) -> BoxHandlerFuture<T, E>,
     ) -> RusotoFuture<T, E>
 {
-    RusotoFuture { state: Some(RusotoFutureState::SignAndDispatch { future, handler }) }
+    new_generic(dispatch, handler)
 }
 
-impl<T, E> RusotoFuture<T, E> {
+/// Build a `RusotoFuture` around a dispatch factory `D` and handler `H`, without boxing
+/// either one. `dispatch` is called once up front and, if `.with_retry()` is used, again on
+/// every retryable failure; it may be any `Fn() -> F` closure, and `handler` may be any
+/// `Fn(HttpResponse) -> R` closure, not just a bare `fn` pointer, so callers can capture
+/// state (credentials, request context) directly instead of re-deriving it on every retry.
+pub fn new_generic<T, E, F, R, H, D>(dispatch: D, handler: H) -> RusotoFuture<T, E, F, R, H, D>
+    where F: TimeoutFuture<Item=HttpResponse, Error=SignAndDispatchError> + Send,
+          H: Fn(HttpResponse) -> R,
+          R: Future<Item=T, Error=E> + Send,
+          D: Fn() -> F
+{
+    let future = dispatch();
+    RusotoFuture {
+        state: Some(RusotoFutureState::SignAndDispatch { future, dispatch, handler, attempt: 0 }),
+        retry_policy: None,
+        timeout: None,
+    }
+}
+
+impl<T, E, F, R, H, D> RusotoFuture<T, E, F, R, H, D>
+    where F: TimeoutFuture<Item=HttpResponse, Error=SignAndDispatchError> + Send
+{
     /// Set the timeout on the future to the provided duration.
     ///
     /// Unlike `set_timeout` this method can be easily chained:
@@ -82,6 +133,7 @@ impl<T, E> RusotoFuture<T, E> {
     /// This is only guaranteed to take effect when called before the future
     /// is polled for the first time.
     pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = Some(timeout);
         if let Some(RusotoFutureState::SignAndDispatch { ref mut future, .. }) = self.state {
             future.set_timeout(timeout);
         }
@@ -92,11 +144,46 @@ impl<T, E> RusotoFuture<T, E> {
     /// This is only guaranteed to take effect when called before the future
     /// is polled for the first time.
     pub fn clear_timeout(&mut self) {
+        self.timeout = None;
         if let Some(RusotoFutureState::SignAndDispatch { ref mut future, .. }) = self.state {
             future.clear_timeout();
         }
     }
 
+    /// Retry the request with the given policy when sign-and-dispatch fails with a
+    /// transient `HttpDispatchError`, or when the parsed response comes back as a retryable
+    /// `E`. A retry re-runs the stored dispatch factory after a `base * multiplier^attempt`
+    /// backoff (capped at `max_delay`, with full jitter). Non-retryable errors and exhausted
+    /// attempts propagate unchanged.
+    pub fn with_retry(mut self, policy: RetryPolicy<E>) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+}
+
+impl<T, E, F, R, H, D> RusotoFuture<T, E, F, R, H, D>
+    where F: TimeoutFuture<Item=HttpResponse, Error=SignAndDispatchError> + Send + 'static,
+          H: Fn(HttpResponse) -> R + Clone + Send + 'static,
+          R: Future<Item=T, Error=E> + Send + 'static,
+          D: Fn() -> F + Send + 'static,
+          T: Send + 'static,
+          E: From<CredentialsError> + From<HttpDispatchError> + Send + 'static
+{
+    /// Erase the concrete dispatch-future, handler, and dispatch-factory types, producing the
+    /// default `RusotoFuture<T, E>` so it can be returned from an API that only names `T` and `E`.
+    pub fn boxed(self) -> RusotoFuture<T, E> {
+        RusotoFuture {
+            state: Some(RusotoFutureState::RunningResponseHandler {
+                future: Box::new(self) as BoxHandlerFuture<T, E>,
+                retry: None,
+            }),
+            retry_policy: None,
+            timeout: None,
+        }
+    }
+}
+
+impl<T, E> RusotoFuture<T, E> {
     /// Blocks the current thread until the future has resolved.
     ///
     /// This is meant to provide a simple way for non-async consumers
@@ -109,34 +196,87 @@ impl<T, E> RusotoFuture<T, E> {
     }
 }
 
-impl<T, E> Future for RusotoFuture<T, E>
-    where E: From<CredentialsError> + From<HttpDispatchError>
+impl<T, E, F, R, H, D> Future for RusotoFuture<T, E, F, R, H, D>
+    where F: TimeoutFuture<Item=HttpResponse, Error=SignAndDispatchError> + Send,
+          H: Fn(HttpResponse) -> R + Clone,
+          R: Future<Item=T, Error=E> + Send,
+          D: Fn() -> F,
+          E: From<CredentialsError> + From<HttpDispatchError>
 {
     type Item = T;
     type Error = E;
 
     fn poll(&mut self) -> Poll<T, E> {
         match self.state.take().unwrap() {
-            RusotoFutureState::SignAndDispatch { mut future, handler } => {
+            RusotoFutureState::SignAndDispatch { mut future, dispatch, handler, attempt } => {
                 match future.poll() {
+                    Err(SignAndDispatchError::Dispatch(err)) => {
+                        let retryable = self.retry_policy.as_ref()
+                            .map(|policy| attempt + 1 < policy.max_attempts
+                                && (policy.classifier)(&RetryableError::Dispatch(&err)))
+                            .unwrap_or(false);
+                        if retryable {
+                            self.schedule_retry(dispatch, handler, attempt);
+                            // Poll the new timer right away so it registers a wakeup.
+                            self.poll()
+                        } else {
+                            Err(err.into())
+                        }
+                    },
                     Err(SignAndDispatchError::Credentials(err)) => Err(err.into()),
-                    Err(SignAndDispatchError::Dispatch(err)) => Err(err.into()),
                     Ok(Async::Ready(response)) => {
-                        self.state = Some(RusotoFutureState::RunningResponseHandler(handler(response)));
+                        let retry = Some((dispatch, handler.clone(), attempt));
+                        self.state = Some(RusotoFutureState::RunningResponseHandler {
+                            future: handler(response),
+                            retry,
+                        });
                         self.poll()
                     },
                     Ok(Async::NotReady) => {
-                        self.state = Some(RusotoFutureState::SignAndDispatch { future, handler });
+                        self.state = Some(RusotoFutureState::SignAndDispatch { future, dispatch, handler, attempt });
                         Ok(Async::NotReady)
                     }
                 }
             },
-            RusotoFutureState::RunningResponseHandler(mut future) => {
-                match future.poll()? {
-                    Async::Ready(value) => Ok(Async::Ready(value)),
-                    Async::NotReady => {
-                        self.state = Some(RusotoFutureState::RunningResponseHandler(future));
+            RusotoFutureState::RunningResponseHandler { mut future, retry } => {
+                match future.poll() {
+                    Ok(Async::Ready(value)) => Ok(Async::Ready(value)),
+                    Ok(Async::NotReady) => {
+                        self.state = Some(RusotoFutureState::RunningResponseHandler { future, retry });
                         Ok(Async::NotReady)
+                    },
+                    Err(err) => {
+                        let retryable = match (retry.as_ref(), self.retry_policy.as_ref()) {
+                            (Some((_, _, attempt)), Some(policy)) =>
+                                attempt + 1 < policy.max_attempts && (policy.classifier)(&RetryableError::Parsed(&err)),
+                            _ => false,
+                        };
+                        if retryable {
+                            let (dispatch, handler, attempt) = retry.unwrap();
+                            self.schedule_retry(dispatch, handler, attempt);
+                            self.poll()
+                        } else {
+                            Err(err)
+                        }
+                    },
+                }
+            },
+            RusotoFutureState::Retrying { dispatch, handler, mut delay, attempt } => {
+                match delay.poll() {
+                    Ok(Async::NotReady) => {
+                        self.state = Some(RusotoFutureState::Retrying { dispatch, handler, delay, attempt });
+                        Ok(Async::NotReady)
+                    },
+                    // A fired or errored timer both mean "stop waiting"; either way, retry now.
+                    Ok(Async::Ready(_)) | Err(_) => {
+                        let mut future = dispatch();
+                        // `dispatch()` builds a brand-new future that knows nothing about any
+                        // timeout the caller configured on the attempt that just failed.
+                        if let Some(timeout) = self.timeout {
+                            future.set_timeout(timeout);
+                        }
+                        self.state = Some(RusotoFutureState::SignAndDispatch { future, dispatch, handler, attempt });
+                        self.poll()
                     }
                 }
             }
@@ -144,19 +284,109 @@ impl<T, E> Future for RusotoFuture<T, E>
     }
 }
 
-enum RusotoFutureState<T, E> {
+impl<T, E, F, R, H, D> RusotoFuture<T, E, F, R, H, D>
+    where D: Fn() -> F
+{
+    /// Stash a `Retrying` state carrying a timer for the next backoff; `poll` re-enters
+    /// `SignAndDispatch` with a freshly dispatched `future` once the timer fires.
+    fn schedule_retry(&mut self, dispatch: D, handler: H, attempt: u32) {
+        let delay_for = self.retry_policy.as_ref().expect("retry_policy set by caller").delay_for(attempt);
+        self.state = Some(RusotoFutureState::Retrying {
+            dispatch,
+            handler,
+            attempt: attempt + 1,
+            delay: Delay::new(Instant::now() + delay_for),
+        });
+    }
+}
+
+enum RusotoFutureState<T, E, F, R, H, D> {
     SignAndDispatch {
-        future: Box<TimeoutFuture<Item=HttpResponse, Error=SignAndDispatchError> + Send>,
-        handler: fn(HttpResponse) -> Box<Future<Item=T, Error=E> + Send>
+        future: F,
+        dispatch: D,
+        handler: H,
+        attempt: u32,
+    },
+    RunningResponseHandler {
+        future: R,
+        // Present only when a retry policy is set; lets a failed `future` be retried from
+        // scratch via `dispatch` with a fresh `handler`.
+        retry: Option<(D, H, u32)>,
     },
-    RunningResponseHandler(Box<Future<Item=T, Error=E> + Send>)
+    Retrying {
+        dispatch: D,
+        handler: H,
+        delay: Delay,
+        attempt: u32,
+    },
+}
+
+/// An error that a `RetryPolicy`'s classifier is asked to judge: either a transport-level
+/// dispatch failure, or the parsed, protocol-level error `E` a service returned.
+pub enum RetryableError<'a, E: 'a> {
+    Dispatch(&'a HttpDispatchError),
+    Parsed(&'a E),
+}
+
+/// Controls automatic retry-with-backoff for a `RusotoFuture`.
+///
+/// Construct with `RetryPolicy::new(classifier)` and tune `max_attempts`/`base_delay`/
+/// `multiplier` as needed; delays are capped at `max_delay` and spread with full jitter.
+pub struct RetryPolicy<E> {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: f64,
+    classifier: fn(&RetryableError<E>) -> bool,
+}
+
+impl<E> RetryPolicy<E> {
+    pub fn new(classifier: fn(&RetryableError<E>) -> bool) -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(20),
+            multiplier: 2.0,
+            classifier,
+        }
+    }
+
+    fn delay_for(&self, attempt: u32) -> Duration {
+        let base_millis = self.base_delay.as_secs() as f64 * 1000.0 + self.base_delay.subsec_millis() as f64;
+        let max_millis = self.max_delay.as_secs() as f64 * 1000.0 + self.max_delay.subsec_millis() as f64;
+        let capped = (base_millis * self.multiplier.powi(attempt as i32)).min(max_millis);
+        let jittered = capped * ::rand::random::<f64>();
+        Duration::from_millis(jittered as u64)
+    }
+}
+
+/// Classify the common AWS throttling errors as retryable: any transport-level dispatch
+/// failure, plus a `Throttling`/`RequestLimitExceeded`-style parsed error whose `Display`
+/// mentions one of those codes. `RetryableError::Parsed` never carries an HTTP status code,
+/// so this can't (and doesn't) key off 5xx — services that want that should pass their own
+/// classifier to `RetryPolicy::new` instead.
+pub fn is_commonly_retryable<E: ::std::fmt::Display>(error: &RetryableError<E>) -> bool {
+    const RETRYABLE_CODES: &[&str] = &["Throttling", "RequestLimitExceeded", "ProvisionedThroughputExceededException"];
+
+    match *error {
+        RetryableError::Dispatch(_) => true,
+        RetryableError::Parsed(err) => {
+            let rendered = err.to_string();
+            RETRYABLE_CODES.iter().any(|code| rendered.contains(code))
+        }
+    }
 }
 
 impl<T: Send + 'static, E: Send + 'static> From<Result<T, E>> for RusotoFuture<T, E> {
     fn from(value: Result<T, E>) -> Self {
         let fut = value.into_future();
         RusotoFuture {
-            state: Some(RusotoFutureState::RunningResponseHandler(Box::new(fut))),
+            state: Some(RusotoFutureState::RunningResponseHandler {
+                future: Box::new(fut),
+                retry: None,
+            }),
+            retry_policy: None,
+            timeout: None,
         }
     }
 }
@@ -182,3 +412,80 @@ fn rusuto_future_from_err() {
         RusotoFuture::from("ab".parse::<i32>().map_err(|e| e.into()));
     assert!(fut.sync().is_err());
 }
+
+/// A fake dispatch-future whose factory decides per-call whether it fails or succeeds, so
+/// `with_retry` can be exercised without a real HTTP stack.
+struct MockDispatchFuture {
+    succeed: bool,
+}
+
+impl Future for MockDispatchFuture {
+    type Item = HttpResponse;
+    type Error = SignAndDispatchError;
+
+    fn poll(&mut self) -> Poll<HttpResponse, SignAndDispatchError> {
+        if self.succeed {
+            Ok(Async::Ready(HttpResponse::default()))
+        } else {
+            Err(SignAndDispatchError::Dispatch(HttpDispatchError::new("simulated dispatch failure")))
+        }
+    }
+}
+
+impl TimeoutFuture for MockDispatchFuture {
+    fn set_timeout(&mut self, _timeout: Duration) {}
+    fn clear_timeout(&mut self) {}
+}
+
+#[test]
+fn rusoto_future_with_retry_retries_until_success() {
+    use std::error::Error;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    type E = Box<Error + Send + Sync>;
+
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let dispatch_attempts = attempts.clone();
+    let dispatch = move || {
+        let attempt = dispatch_attempts.fetch_add(1, Ordering::SeqCst);
+        MockDispatchFuture { succeed: attempt >= 2 }
+    };
+
+    let handler = |_response: HttpResponse| -> BoxHandlerFuture<u32, E> {
+        Box::new(Ok(1u32).into_future())
+    };
+
+    let mut policy = RetryPolicy::new(is_commonly_retryable::<E>);
+    policy.base_delay = Duration::from_millis(1);
+    policy.max_delay = Duration::from_millis(5);
+
+    let fut = new_generic(dispatch, handler).with_retry(policy);
+    let result: Result<u32, E> = fut.boxed().sync();
+
+    assert_eq!(result.unwrap(), 1);
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn rusoto_future_with_retry_propagates_error_once_attempts_exhausted() {
+    use std::error::Error;
+
+    type E = Box<Error + Send + Sync>;
+
+    let dispatch = move || MockDispatchFuture { succeed: false };
+
+    let handler = |_response: HttpResponse| -> BoxHandlerFuture<u32, E> {
+        Box::new(Ok(1u32).into_future())
+    };
+
+    let mut policy = RetryPolicy::new(is_commonly_retryable::<E>);
+    policy.max_attempts = 2;
+    policy.base_delay = Duration::from_millis(1);
+    policy.max_delay = Duration::from_millis(5);
+
+    let fut = new_generic(dispatch, handler).with_retry(policy);
+    let result: Result<u32, E> = fut.boxed().sync();
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,20 @@
+//! Pluggable TLS backends for `HttpClient`.
+//!
+//! By default `HttpClient` dispatches requests over a native-TLS `hyper::Client`. Building
+//! with the `rustls` feature makes a `rustls`-backed constructor available as well, for
+//! platforms where linking OpenSSL/native-TLS is painful.
+#![cfg(feature = "rustls")]
+
+use hyper_rustls::HttpsConnector as RustlsConnector;
+
+use super::request::{HttpClient, TlsError};
+
+impl HttpClient<RustlsConnector> {
+    /// Create an `HttpClient` backed by `rustls` with OS trust roots loaded via
+    /// `rustls-native-certs`, instead of the default native-TLS `hyper::Client`.
+    pub fn new_with_rustls() -> Result<Self, TlsError> {
+        let connector = RustlsConnector::with_native_roots()
+            .map_err(|e| TlsError(e.to_string()))?;
+        Ok(HttpClient::from_connector(connector))
+    }
+}